@@ -0,0 +1,136 @@
+//! Self-describing frame format for round-tripping compressed data without
+//! needing to transmit the original length out of band.
+//!
+//! A frame is laid out as:
+//!
+//! ```text
+//! magic (2 bytes) | version (1 byte) | original length (8 bytes, LE) | checksum (4 bytes, LE) | compressed payload
+//! ```
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::{decompress_into, LzoContext, LzoError};
+
+const MAGIC: [u8; 2] = *b"LZ";
+const VERSION: u8 = 1;
+const HEADER_LEN: usize = MAGIC.len() + 1 + 8 + 4;
+
+/// Upper bound on the original length a frame header is allowed to claim,
+/// well above any payload `compress_frame` would reasonably produce. Guards
+/// against a forged or corrupt header driving an unbounded allocation before
+/// the checksum is ever checked — frames are meant for file and network use,
+/// where the header can't be trusted.
+const MAX_FRAME_LEN: usize = 16 * 1024 * 1024;
+
+/// Compresses `input` into a self-describing frame: a small header carrying
+/// the magic/version, the original length, and a checksum, followed by the
+/// compressed payload.
+///
+/// The resulting buffer can be round-tripped with [`decompress_frame`]
+/// without the caller needing to know or store the uncompressed size.
+/// Inputs longer than `MAX_FRAME_LEN` (16 MiB) are rejected with
+/// [`LzoError::InvalidArgument`], since [`decompress_frame`] would refuse
+/// their header.
+pub fn compress_frame(input: &[u8]) -> Result<Vec<u8>, LzoError> {
+    if input.len() > MAX_FRAME_LEN {
+        return Err(LzoError::InvalidArgument);
+    }
+    let payload = LzoContext::new().compress(input)?;
+
+    let mut frame = Vec::with_capacity(HEADER_LEN + payload.len());
+    frame.extend_from_slice(&MAGIC);
+    frame.push(VERSION);
+    frame.extend_from_slice(&(input.len() as u64).to_le_bytes());
+    frame.extend_from_slice(&checksum(input).to_le_bytes());
+    frame.extend_from_slice(&payload);
+
+    Ok(frame)
+}
+
+/// Decompresses a frame produced by [`compress_frame`], sizing the output
+/// buffer from the length stored in the header and verifying the checksum
+/// stored in the header against the decompressed data.
+///
+/// Returns [`LzoError::Error`] if the header is malformed, the magic/version
+/// don't match, or the checksum doesn't match the decompressed data.
+pub fn decompress_frame(frame: &[u8]) -> Result<Vec<u8>, LzoError> {
+    if frame.len() < HEADER_LEN || frame[0..2] != MAGIC || frame[2] != VERSION {
+        return Err(LzoError::Error);
+    }
+
+    let original_len = u64::from_le_bytes(frame[3..11].try_into().unwrap()) as usize;
+    if original_len > MAX_FRAME_LEN {
+        return Err(LzoError::Error);
+    }
+
+    let expected_checksum = u32::from_le_bytes(frame[11..15].try_into().unwrap());
+    let payload = &frame[HEADER_LEN..];
+
+    let mut output = vec![0u8; original_len];
+    let len = decompress_into(payload, &mut output)?;
+    output.truncate(len);
+
+    if checksum(&output) != expected_checksum {
+        return Err(LzoError::Error);
+    }
+
+    Ok(output)
+}
+
+/// A small, dependency-free checksum used to detect corruption of the
+/// decompressed payload. Not cryptographically secure.
+fn checksum(data: &[u8]) -> u32 {
+    let mut a: u32 = 1;
+    let mut b: u32 = 0;
+    for &byte in data {
+        a = a.wrapping_add(byte as u32) % 65521;
+        b = b.wrapping_add(a) % 65521;
+    }
+    (b << 16) | a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{compress_frame, decompress_frame, MAX_FRAME_LEN};
+    use crate::LzoError;
+
+    #[test]
+    fn roundtrip() {
+        let input = b"test123456789".repeat(100);
+        let frame = compress_frame(&input).unwrap();
+        let output = decompress_frame(&frame).unwrap();
+        assert_eq!(output, input);
+    }
+
+    #[test]
+    fn roundtrip_empty_input() {
+        let frame = compress_frame(&[]).unwrap();
+        let output = decompress_frame(&frame).unwrap();
+        assert!(output.is_empty());
+    }
+
+    #[test]
+    fn rejects_bad_magic() {
+        let mut frame = compress_frame(b"hello world").unwrap();
+        frame[0] = !frame[0];
+        assert_eq!(decompress_frame(&frame), Err(LzoError::Error));
+    }
+
+    #[test]
+    fn rejects_checksum_mismatch() {
+        let input = b"hello world".repeat(10);
+        let mut frame = compress_frame(&input).unwrap();
+        // Flip a byte in the stored checksum so it no longer matches the
+        // (correctly) decompressed data.
+        frame[11] ^= 0xFF;
+        assert_eq!(decompress_frame(&frame), Err(LzoError::Error));
+    }
+
+    #[test]
+    fn rejects_original_len_over_max_frame_len() {
+        let mut frame = compress_frame(b"hello world").unwrap();
+        frame[3..11].copy_from_slice(&((MAX_FRAME_LEN + 1) as u64).to_le_bytes());
+        assert_eq!(decompress_frame(&frame), Err(LzoError::Error));
+    }
+}
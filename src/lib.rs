@@ -1,36 +1,182 @@
-use std::error::Error;
-use std::ffi::c_void;
-use std::os::raw::c_uchar;
-use std::ptr::null_mut;
+//! Bindings to minilzo's LZO1X-1 compressor.
+//!
+//! By default this crate depends on `std`. Disable default features and
+//! enable `alloc` to use the `Vec`-returning APIs (`compress`/`decompress`,
+//! [`LzoContext`], the frame format) on targets that have a global allocator
+//! but no `std`. With both features off, only the allocation-free
+//! `compress_into`/`decompress_into` slice APIs, their sizing helpers
+//! ([`output_buffer_size`], [`LZO1X_1_MEM_COMPRESS`]) and [`LzoError`] are
+//! available.
+#![cfg_attr(not(feature = "std"), no_std)]
 
-const LZO1X_1_MEM_COMPRESS: usize = 16384 * std::mem::size_of::<usize>();
+#[cfg(feature = "alloc")]
+extern crate alloc;
 
+use core::ffi::c_void;
+use core::ffi::c_uchar;
+use core::ptr::null_mut;
+
+#[cfg(feature = "alloc")]
+use alloc::boxed::Box;
+#[cfg(feature = "alloc")]
+use alloc::vec;
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+#[cfg(feature = "alloc")]
+mod frame;
+#[cfg(feature = "std")]
+mod stream;
+
+#[cfg(feature = "alloc")]
+pub use frame::{compress_frame, decompress_frame};
+#[cfg(feature = "std")]
+pub use stream::{LzoDecoder, LzoEncoder};
+
+/// The size `wrkmem` must be in [`compress_into`] and [`LzoContext`]'s
+/// internal buffer (≈128 KB on 64-bit targets).
+pub const LZO1X_1_MEM_COMPRESS: usize = 16384 * core::mem::size_of::<usize>();
+
+#[cfg(feature = "alloc")]
 pub fn compress(input: &[u8]) -> Result<Vec<u8>, LzoError> {
-    let mut output = vec![0u8; output_buffer_size(input.len())];
-    let mut wrkmem = [0u8; LZO1X_1_MEM_COMPRESS];
+    LzoContext::new().compress(input)
+}
+
+/// Compresses `input` into the caller-provided `output` buffer, returning the
+/// number of bytes written.
+///
+/// `output` must be at least [`output_buffer_size(input.len())`](output_buffer_size)
+/// bytes long, otherwise [`LzoError::OutputOverrun`] is returned before the
+/// underlying compressor runs. `wrkmem` must be at least
+/// [`LZO1X_1_MEM_COMPRESS`] bytes long, otherwise [`LzoError::InvalidArgument`]
+/// is returned; it is the scratch buffer the compressor needs and should be
+/// reused across calls (see [`LzoContext`]) to avoid repeated allocation.
+///
+/// Unlike [`compress`], this never touches the allocator, making it suitable
+/// for pooled-buffer or arena-based workflows.
+pub fn compress_into(input: &[u8], output: &mut [u8], wrkmem: &mut [u8]) -> Result<usize, LzoError> {
+    if output.len() < output_buffer_size(input.len()) {
+        return Err(LzoError::OutputOverrun);
+    }
+    if wrkmem.len() < LZO1X_1_MEM_COMPRESS {
+        return Err(LzoError::InvalidArgument);
+    }
 
     let mut size = output.len();
-    let error = unsafe { lzo1x_1_compress(input.as_ptr(), input.len(), output.as_mut_ptr(), &mut size, wrkmem.as_mut_ptr() as *mut c_void) };
+    let error = unsafe {
+        lzo1x_1_compress(input.as_ptr(), input.len(), output.as_mut_ptr(), &mut size, wrkmem.as_mut_ptr() as *mut c_void)
+    };
     if LzoError::Ok == error {
-        output.resize(size, 0);
-        Ok(output)
+        Ok(size)
     } else {
         Err(error)
     }
 }
 
-pub fn decompress(buffer_len: usize, data: &[u8]) -> Result<Vec<u8>, LzoError> {
-    let mut output = vec![0u8; buffer_len];
-    let mut output_len = buffer_len;
+/// Decompresses `data` into the caller-provided `output` buffer, returning
+/// the number of bytes written.
+///
+/// Unlike [`decompress`], this never touches the allocator, making it
+/// suitable for pooled-buffer or arena-based workflows. If `output` is too
+/// small to hold the decompressed data, [`LzoError::OutputOverrun`] is
+/// returned.
+pub fn decompress_into(data: &[u8], output: &mut [u8]) -> Result<usize, LzoError> {
+    let mut output_len = output.len();
     let error = unsafe { lzo1x_decompress_safe(data.as_ptr(), data.len(), output.as_mut_ptr(), &mut output_len, null_mut()) };
     if LzoError::Ok == error {
-        output.resize(output_len, 0);
-        Ok(output)
+        Ok(output_len)
     } else {
         Err(error)
     }
 }
 
+/// A reusable compression context.
+///
+/// Creating a context allocates the `wrkmem` scratch buffer required by the
+/// LZO1X-1 algorithm (≈128 KB on 64-bit targets) once, up front, so that
+/// repeated calls to [`compress`](LzoContext::compress) don't each pay for a
+/// fresh allocation and zeroing of that buffer.
+#[cfg(feature = "alloc")]
+pub struct LzoContext {
+    wrkmem: Box<[u8]>,
+}
+
+#[cfg(feature = "alloc")]
+impl LzoContext {
+    /// Creates a new context with a freshly allocated `wrkmem` buffer.
+    pub fn new() -> Self {
+        Self {
+            wrkmem: vec![0u8; LZO1X_1_MEM_COMPRESS].into_boxed_slice(),
+        }
+    }
+
+    /// Compresses `input`, allocating a new `Vec` for the result.
+    ///
+    /// Reuses this context's `wrkmem` buffer instead of allocating one, so
+    /// calling this repeatedly on the same context is cheaper than calling
+    /// the free-standing [`compress`] function in a loop.
+    pub fn compress(&mut self, input: &[u8]) -> Result<Vec<u8>, LzoError> {
+        let mut output = vec![0u8; output_buffer_size(input.len())];
+        let size = compress_into(input, &mut output, &mut self.wrkmem)?;
+        output.resize(size, 0);
+        Ok(output)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl Default for LzoContext {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "alloc")]
+pub fn decompress(buffer_len: usize, data: &[u8]) -> Result<Vec<u8>, LzoError> {
+    let mut output = vec![0u8; buffer_len];
+    let output_len = decompress_into(data, &mut output)?;
+    output.resize(output_len, 0);
+    Ok(output)
+}
+
+/// Compresses `input` using `dict` as a preset history window.
+///
+/// The underlying `lzo1x_1_compress` has no native preset-dictionary
+/// support, so this works by prepending `dict` to `input` and compressing
+/// the combined buffer: back-references into `dict` let small records that
+/// share content with it compress better than `input` would alone. The
+/// trade-off is that `dict` is re-encoded into the output on every call, so
+/// this only pays off when `dict` is small relative to `input` — it is not
+/// a substitute for a real streaming preset dictionary shared across many
+/// calls. Use [`decompress_with_dict`] with the same `dict` to recover `input`.
+#[cfg(feature = "alloc")]
+pub fn compress_with_dict(input: &[u8], dict: &[u8]) -> Result<Vec<u8>, LzoError> {
+    let mut windowed = Vec::with_capacity(dict.len() + input.len());
+    windowed.extend_from_slice(dict);
+    windowed.extend_from_slice(input);
+    LzoContext::new().compress(&windowed)
+}
+
+/// Decompresses `data` produced by [`compress_with_dict`] with the same `dict`.
+///
+/// `buffer_len` is the original, undictionaried length of the compressed
+/// input (i.e. the `input.len()` passed to [`compress_with_dict`]). The
+/// dictionary bytes are verified against `dict` and stripped from the
+/// returned `Vec`; [`LzoError::Error`] is returned if they don't match,
+/// which catches a caller passing a different dictionary than was used to
+/// compress.
+#[cfg(feature = "alloc")]
+pub fn decompress_with_dict(data: &[u8], buffer_len: usize, dict: &[u8]) -> Result<Vec<u8>, LzoError> {
+    let mut windowed = vec![0u8; dict.len() + buffer_len];
+    let windowed_len = decompress_into(data, &mut windowed)?;
+    let data_len = windowed_len.checked_sub(dict.len()).ok_or(LzoError::Error)?;
+    if windowed[..dict.len()] != *dict {
+        return Err(LzoError::Error);
+    }
+    windowed.drain(..dict.len());
+    windowed.resize(data_len, 0);
+    Ok(windowed)
+}
+
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd)]
 #[repr(i32)]
 pub enum LzoError {
@@ -54,8 +200,8 @@ pub enum LzoError {
     InternalError = -99,
 }
 
-impl std::fmt::Display for LzoError {
-    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+impl core::fmt::Display for LzoError {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter) -> core::fmt::Result {
         let s = match self {
             LzoError::Ok => { "ok" }
             LzoError::Error => { "error" }
@@ -76,10 +222,13 @@ impl std::fmt::Display for LzoError {
     }
 }
 
-impl Error for LzoError {}
+#[cfg(feature = "std")]
+impl std::error::Error for LzoError {}
 
+/// Returns the worst-case size of the output buffer required to compress
+/// `input_size` bytes.
 #[inline]
-const fn output_buffer_size(input_size: usize) -> usize {
+pub const fn output_buffer_size(input_size: usize) -> usize {
     input_size + (input_size / 16) + 64 + 3
 }
 
@@ -100,10 +249,10 @@ extern "C" {
 
 #[cfg(test)]
 mod tests {
-    use std::ffi::c_void;
-    use std::ptr::null_mut;
+    use core::ffi::c_void;
+    use core::ptr::null_mut;
 
-    use crate::{lzo1x_1_compress, LZO1X_1_MEM_COMPRESS, lzo1x_decompress_safe, LzoError, output_buffer_size, compress};
+    use crate::{lzo1x_1_compress, LZO1X_1_MEM_COMPRESS, lzo1x_decompress_safe, LzoError, output_buffer_size};
 
     #[test]
     pub fn test() {
@@ -122,4 +271,89 @@ mod tests {
 
         assert_eq!(data[..data_len], input);
     }
+
+    #[test]
+    fn compress_into_decompress_into_roundtrip() {
+        let input = b"test123456789".repeat(100);
+        let mut output = vec![0u8; output_buffer_size(input.len())];
+        let mut wrkmem = vec![0u8; LZO1X_1_MEM_COMPRESS];
+
+        let size = crate::compress_into(&input, &mut output, &mut wrkmem).unwrap();
+
+        let mut decompressed = vec![0u8; input.len()];
+        let len = crate::decompress_into(&output[..size], &mut decompressed).unwrap();
+
+        assert_eq!(decompressed[..len], input);
+    }
+
+    #[test]
+    fn compress_into_decompress_into_roundtrip_empty_input() {
+        let input: &[u8] = b"";
+        let mut output = vec![0u8; output_buffer_size(input.len())];
+        let mut wrkmem = vec![0u8; LZO1X_1_MEM_COMPRESS];
+
+        let size = crate::compress_into(input, &mut output, &mut wrkmem).unwrap();
+
+        let mut decompressed = vec![0u8; 0];
+        let len = crate::decompress_into(&output[..size], &mut decompressed).unwrap();
+
+        assert_eq!(len, 0);
+    }
+
+    #[test]
+    fn compress_into_rejects_undersized_wrkmem() {
+        let input = b"hello world";
+        let mut output = vec![0u8; output_buffer_size(input.len())];
+        let mut wrkmem = vec![0u8; LZO1X_1_MEM_COMPRESS - 1];
+
+        let result = crate::compress_into(input, &mut output, &mut wrkmem);
+
+        assert_eq!(result, Err(LzoError::InvalidArgument));
+    }
+
+    #[test]
+    fn compress_into_rejects_undersized_output() {
+        let input = b"hello world".repeat(10);
+        let mut output = vec![0u8; output_buffer_size(input.len()) - 1];
+        let mut wrkmem = vec![0u8; LZO1X_1_MEM_COMPRESS];
+
+        let result = crate::compress_into(&input, &mut output, &mut wrkmem);
+
+        assert_eq!(result, Err(LzoError::OutputOverrun));
+    }
+
+    #[test]
+    fn lzo_context_reuse_roundtrips() {
+        let mut ctx = crate::LzoContext::new();
+
+        for message in [b"first message".to_vec(), b"second message".to_vec(), Vec::new()] {
+            let compressed = ctx.compress(&message).unwrap();
+            let decompressed = crate::decompress(message.len(), &compressed).unwrap();
+            assert_eq!(decompressed, message);
+        }
+    }
+
+    #[test]
+    fn compress_with_dict_roundtrips() {
+        let dict = b"common shared prefix used across many small records".repeat(4);
+        let input = b"a small record";
+
+        let compressed = crate::compress_with_dict(input, &dict).unwrap();
+        let decompressed = crate::decompress_with_dict(&compressed, input.len(), &dict).unwrap();
+
+        assert_eq!(decompressed, input);
+    }
+
+    #[test]
+    fn decompress_with_dict_rejects_mismatched_dict() {
+        let dict = b"the dict used at compress time".to_vec();
+        let wrong_dict = b"another dict of the same size.".to_vec();
+        assert_eq!(dict.len(), wrong_dict.len());
+        let input = b"a small record";
+
+        let compressed = crate::compress_with_dict(input, &dict).unwrap();
+        let result = crate::decompress_with_dict(&compressed, input.len(), &wrong_dict);
+
+        assert_eq!(result, Err(LzoError::Error));
+    }
 }
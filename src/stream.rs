@@ -0,0 +1,213 @@
+//! Streaming `Read`/`Write` wrappers that transparently chunk data into
+//! independently-compressed blocks, so whole payloads never need to be
+//! buffered up front.
+//!
+//! Each block on the wire looks like:
+//!
+//! ```text
+//! uncompressed length (4 bytes, LE) | compressed length (4 bytes, LE) | compressed payload
+//! ```
+
+use std::io::{self, Read, Write};
+
+use crate::{decompress_into, output_buffer_size, LzoContext};
+
+const DEFAULT_BLOCK_SIZE: usize = 64 * 1024;
+const BLOCK_HEADER_LEN: usize = 8;
+
+/// Upper bound on the uncompressed length of a single block [`LzoDecoder`]
+/// will accept, well above any size [`LzoEncoder`] would reasonably produce.
+/// Guards against a corrupt or hostile stream driving an unbounded
+/// allocation via a forged block header.
+const MAX_BLOCK_LEN: usize = 16 * 1024 * 1024;
+
+fn to_io_error(err: crate::LzoError) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, err)
+}
+
+/// A `Write` wrapper that compresses everything written to it in
+/// `block_size`-sized chunks and forwards the compressed blocks to `inner`.
+///
+/// Call [`finish`](LzoEncoder::finish) once done writing to flush any
+/// partial block still buffered and recover the underlying writer.
+pub struct LzoEncoder<W: Write> {
+    inner: W,
+    ctx: LzoContext,
+    pending: Vec<u8>,
+    block_size: usize,
+}
+
+impl<W: Write> LzoEncoder<W> {
+    /// Creates an encoder that splits the stream into blocks of the default size.
+    pub fn new(inner: W) -> Self {
+        Self::with_block_size(inner, DEFAULT_BLOCK_SIZE)
+    }
+
+    /// Creates an encoder that splits the stream into blocks of `block_size` bytes.
+    pub fn with_block_size(inner: W, block_size: usize) -> Self {
+        Self {
+            inner,
+            ctx: LzoContext::new(),
+            pending: Vec::with_capacity(block_size),
+            block_size,
+        }
+    }
+
+    fn write_block(&mut self, block: &[u8]) -> io::Result<()> {
+        let compressed = self.ctx.compress(block).map_err(to_io_error)?;
+
+        self.inner.write_all(&(block.len() as u32).to_le_bytes())?;
+        self.inner.write_all(&(compressed.len() as u32).to_le_bytes())?;
+        self.inner.write_all(&compressed)?;
+        Ok(())
+    }
+
+    /// Flushes any buffered data as a final, possibly short, block and
+    /// returns the underlying writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        if !self.pending.is_empty() {
+            let block = std::mem::take(&mut self.pending);
+            self.write_block(&block)?;
+        }
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+}
+
+impl<W: Write> Write for LzoEncoder<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.pending.extend_from_slice(buf);
+        while self.pending.len() >= self.block_size {
+            let block: Vec<u8> = self.pending.drain(..self.block_size).collect();
+            self.write_block(&block)?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// A `Read` wrapper that decompresses blocks written by [`LzoEncoder`] as
+/// they're needed, refilling its internal buffer one block at a time.
+pub struct LzoDecoder<R: Read> {
+    inner: R,
+    buf: Vec<u8>,
+    pos: usize,
+}
+
+impl<R: Read> LzoDecoder<R> {
+    /// Creates a decoder that reads blocks produced by [`LzoEncoder`] from `inner`.
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            buf: Vec::new(),
+            pos: 0,
+        }
+    }
+
+    /// Reads and decompresses the next block, returning `false` at a clean
+    /// end of stream (no more blocks).
+    fn fill_block(&mut self) -> io::Result<bool> {
+        let mut header = [0u8; BLOCK_HEADER_LEN];
+        if !read_exact_or_eof(&mut self.inner, &mut header)? {
+            return Ok(false);
+        }
+
+        let uncompressed_len = u32::from_le_bytes(header[0..4].try_into().unwrap()) as usize;
+        let compressed_len = u32::from_le_bytes(header[4..8].try_into().unwrap()) as usize;
+
+        if uncompressed_len > MAX_BLOCK_LEN || compressed_len > output_buffer_size(MAX_BLOCK_LEN) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "block length in stream header exceeds MAX_BLOCK_LEN",
+            ));
+        }
+
+        let mut compressed = vec![0u8; compressed_len];
+        self.inner.read_exact(&mut compressed)?;
+
+        self.buf.resize(uncompressed_len, 0);
+        let written = decompress_into(&compressed, &mut self.buf).map_err(to_io_error)?;
+        self.buf.truncate(written);
+        self.pos = 0;
+        Ok(true)
+    }
+}
+
+impl<R: Read> Read for LzoDecoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.pos >= self.buf.len() && !self.fill_block()? {
+            return Ok(0);
+        }
+
+        let n = buf.len().min(self.buf.len() - self.pos);
+        buf[..n].copy_from_slice(&self.buf[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+/// Like [`Read::read_exact`], but returns `Ok(false)` instead of erroring
+/// when the stream ends cleanly before any bytes of `buf` are read.
+fn read_exact_or_eof<R: Read>(reader: &mut R, buf: &mut [u8]) -> io::Result<bool> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) if filled == 0 => return Ok(false),
+            Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+            Ok(n) => filled += n,
+            Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+
+    use super::{LzoDecoder, LzoEncoder, MAX_BLOCK_LEN};
+
+    #[test]
+    fn roundtrip_multiple_blocks() {
+        let input = b"test123456789".repeat(1000);
+
+        let mut encoder = LzoEncoder::with_block_size(Vec::new(), 1024);
+        encoder.write_all(&input).unwrap();
+        let compressed = encoder.finish().unwrap();
+
+        let mut decoder = LzoDecoder::new(compressed.as_slice());
+        let mut output = Vec::new();
+        decoder.read_to_end(&mut output).unwrap();
+
+        assert_eq!(output, input);
+    }
+
+    #[test]
+    fn roundtrip_empty_stream() {
+        let encoder = LzoEncoder::new(Vec::new());
+        let compressed = encoder.finish().unwrap();
+
+        let mut decoder = LzoDecoder::new(compressed.as_slice());
+        let mut output = Vec::new();
+        decoder.read_to_end(&mut output).unwrap();
+
+        assert!(output.is_empty());
+    }
+
+    #[test]
+    fn rejects_block_header_over_max_len() {
+        let mut forged = Vec::new();
+        forged.extend_from_slice(&((MAX_BLOCK_LEN + 1) as u32).to_le_bytes());
+        forged.extend_from_slice(&0u32.to_le_bytes());
+
+        let mut decoder = LzoDecoder::new(forged.as_slice());
+        let mut output = Vec::new();
+        let err = decoder.read_to_end(&mut output).unwrap_err();
+
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    }
+}